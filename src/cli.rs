@@ -1,5 +1,6 @@
 use colored::*;
 
+use std::collections::HashSet;
 use std::env;
 use std::fs;
 use std::process;
@@ -19,6 +20,12 @@ pub fn handle_args() -> Parameters {
 
   let args: Vec<String> = env::args().collect();
 
+  // If `nocolor` is an argument, don't colour the output, for example when
+  // writing it to a file. Done first so every message is affected.
+  if args.contains(&"nocolor".to_string()) {
+    colored::control::set_override(false);
+  }
+
   // If there are no arguments
   if args.len() < FILEPATH_ARG_INDEX+1 {
     println!("{}", "Kathleen Programming Language Compiler\n".green());
@@ -29,6 +36,8 @@ pub fn handle_args() -> Parameters {
   if args.contains(&"help".to_string()) {
     print_help();
   }
+  // If `check` is an argument, only look for errors.
+  let check: bool = args.contains(&"check".to_string());
 
   // Set source file (file_path) and output file (output_path) paths.
   let file_path = &args[FILEPATH_ARG_INDEX];
@@ -43,7 +52,7 @@ pub fn handle_args() -> Parameters {
     // Throw the error saying it doesnt
     error::print_error(
       error::ErrorCode::InvalidFile,
-      Token { token: "".to_string(), line: 0, token_number: 0 },
+      Token::new(""),
       &format!("File not found: {}", file_path.green()),
     );
     error::throw_errors();
@@ -53,13 +62,18 @@ pub fn handle_args() -> Parameters {
   if last_three_chars != "lk.".to_string() {
     error::print_error(
       error::ErrorCode::InvalidFileWarning,
-      Token { token: "".to_string(), line: 0, token_number: 0 },
+      Token::new(""),
       "File does not end with `.kl` extention. Are you sure this is a kathleen file?")
   }
 
   let mut noasm: bool = false;
   let mut nolink: bool = false;
   let mut keep: bool = false;
+  let mut time: bool = false;
+  let mut defines: HashSet<String> = HashSet::new();
+  let mut emit: Option<EmitStage> = None;
+  let mut max_errors: Option<usize> = None;
+  let mut context_lines: usize = 1;
 
   // If there are 3 or more args
   if args.len() > EXTRA_ARGS_INDEX {
@@ -79,6 +93,46 @@ pub fn handle_args() -> Parameters {
           // Keep intermediate files.
           keep = true;
         }
+        "time" => {
+          // Show how long each step of the compilation takes.
+          time = true;
+        }
+        "check" | "nocolor" => {
+          // Already handled above.
+        }
+        option if option.starts_with("define=") => {
+          // Define a flag for `#if` conditional compilation.
+          defines.insert(option["define=".len()..].to_string());
+        }
+        option if option.starts_with("emit=") => {
+          // Show the output of one step of the compilation and stop there.
+          match &option["emit=".len()..] {
+            "tokens" => emit = Some(EmitStage::Tokens),
+            "ir"     => emit = Some(EmitStage::Ir),
+            "nar"    => emit = Some(EmitStage::Nar),
+            _ => {
+              println!("Unknown emit step, expected tokens, ir or nar");
+            }
+          }
+        }
+        option if option.starts_with("maxerrors=") => {
+          // Only show the first few errors.
+          match option["maxerrors=".len()..].parse::<usize>() {
+            Ok(max) => max_errors = Some(max),
+            Err(_) => {
+              println!("maxerrors expects a number, for example maxerrors=10");
+            }
+          }
+        }
+        option if option.starts_with("context=") => {
+          // How many lines to show around errors.
+          match option["context=".len()..].parse::<usize>() {
+            Ok(lines) => context_lines = lines,
+            Err(_) => {
+              println!("context expects a number, for example context=3");
+            }
+          }
+        }
         _ => {
           println!("Unknown arg");
         }
@@ -91,6 +145,12 @@ pub fn handle_args() -> Parameters {
     dont_assemble:           noasm,
     dont_link:               nolink,
     keep_intermediate_files: keep,
+    check_only:              check,
+    show_phase_times:        time,
+    emit,
+    defines,
+    max_errors,
+    context_lines,
   };
 }
 
@@ -102,6 +162,29 @@ pub struct Parameters {
   pub dont_assemble:           bool,
   pub dont_link:               bool,
   pub keep_intermediate_files: bool,
+  /// Stop after looking for errors, don't generate any output.
+  pub check_only:              bool,
+  /// Show how long each step of the compilation took.
+  pub show_phase_times:        bool,
+  /// Flags defined for `#if` conditional compilation.
+  pub defines:                 HashSet<String>,
+  /// Which step's output to show, if any. Compilation stops after that step.
+  pub emit:                    Option<EmitStage>,
+  /// How many errors and warnings to show at most, if limited.
+  pub max_errors:              Option<usize>,
+  /// How many lines of code to show before and after the line an error is on.
+  pub context_lines:           usize,
+}
+
+/// The steps of the compilation whose output can be shown with `emit=<step>`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EmitStage {
+  /// The tokens, from the tokenizer.
+  Tokens,
+  /// The intermediate representation.
+  Ir,
+  /// The near assembly representation.
+  Nar,
 }
 
 /// This function shows a help menu with all possible
@@ -122,10 +205,18 @@ fn print_help() -> ! {
   println!("    - {} {}", "noasm".green(), "  Stop the compilation before it assembles the output file.");
   println!("    - {} {}", "nolink".green(), " Stop the compilation before it links the output file.");
   println!("    - {} {}", "keep".green(), "   Don't delete intermediate files (output.asm, output.o).");
+  println!("    - {} {}", "check".green(), "  Only look for errors in the source file, don't compile it.");
+  println!("    - {} {}", "time".green(), "   Show how long each step of the compilation takes.");
+  println!("    - {} {}", "nocolor".green(), "Don't colour the output.");
+  println!("    - {} {}", "define=FLAG".green(), "Define FLAG for `#if FLAG` conditional compilation.");
+  println!("    - {} {}", "emit=STEP".green(), "  Show the output of STEP (tokens, ir or nar) and stop there.");
+  println!("    - {} {}", "maxerrors=N".green(), "Only show the first N errors and warnings.");
+  println!("    - {} {}", "context=N".green(), "  Show N lines of code around errors (1 if not provided).");
   println!("Example usage:");
   println!("    {}", "kathleen hello.kl hello keep".green());
   println!("    {}", "            |       |     |");
   println!("    {}", "         source   output  keep the assembly output and object file as well");
+  println!("    {}", "kathleen hello.kl check".green());
   println!();
   process::exit(1);
 }
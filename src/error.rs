@@ -1,15 +1,72 @@
 extern crate colored;
 use colored::*;
 
-use std::fs::read_to_string;
 use crate::tokenizer::Token;
-use crate::cli::FILEPATH_ARG_INDEX;
-use std::env;
 use std::process;
+use std::sync::OnceLock;
 
 static mut ERROR_COUNT: usize = 0;
 static mut WARNING_COUNT: usize = 0;
+/// How many errors and warnings `print_error` shows at most, set with `set_max_errors`.
+/// The ones past that are still counted.
+static mut MAX_ERRORS: Option<usize> = None;
+/// Whether the program is only checked for errors and not compiled, set with `set_check_only`.
+static mut CHECK_ONLY: bool = false;
 const WARNING_COLOUR: CustomColor = CustomColor { r: 255, g: 200, b: 50};
+/// How many lines of code to show before and after the line an error is on,
+/// set with `set_context_lines`.
+static mut CONTEXT_LINES: usize = 1;
+/// The code being compiled, set once by `set_source_file` so the lines
+/// shown with errors don't have to be read from the file again.
+static SOURCE_FILE: OnceLock<SourceFile> = OnceLock::new();
+
+/// The lines of the code being compiled.
+pub struct SourceFile {
+  lines: Vec<String>,
+}
+
+impl SourceFile {
+  pub fn new(lines: Vec<String>) -> SourceFile {
+    SourceFile { lines }
+  }
+
+  /// The original text of a line, `None` if the file is not that long.
+  /// Like in `Token`, the first line is line 0.
+  pub fn line_text(&self, line: usize) -> Option<&str> {
+    self.lines.get(line).map(String::as_str)
+  }
+
+  pub fn line_count(&self) -> usize {
+    self.lines.len()
+  }
+}
+
+/// Only show the first `max_errors` errors and warnings, for files with lots of them.
+pub fn set_max_errors(max_errors: Option<usize>) {
+  unsafe { MAX_ERRORS = max_errors; }
+}
+
+/// Show `context_lines` lines of code before and after the line an error is on.
+pub fn set_context_lines(context_lines: usize) {
+  unsafe { CONTEXT_LINES = context_lines; }
+}
+
+/// Tell `throw_errors` that the program is only checked, so it doesn't say compilation starts.
+pub fn set_check_only(check_only: bool) {
+  unsafe { CHECK_ONLY = check_only; }
+}
+
+/// How many errors were printed so far, for tests to check an error was found.
+#[cfg(test)]
+pub fn error_count() -> usize {
+  unsafe { ERROR_COUNT }
+}
+
+/// Keep the lines of the code being compiled for `show_lines`.
+/// Only the first call does anything.
+pub fn set_source_file(lines: Vec<String>) {
+  let _ = SOURCE_FILE.set(SourceFile::new(lines));
+}
 
 /// used in the `throw_error()`
 pub enum ErrorCode {
@@ -24,6 +81,43 @@ pub enum ErrorCode {
   InvalidFile,
   InvalidFileWarning,
   CannotFindCounterpart,
+  /// Error code for a string that is still open at the end of the line, for example
+  /// `const hello str = "Hello;`
+  UnterminatedString,
+  /// Error code for an invisible character, like a zero width space, outside of a string.
+  InvisibleCharacter,
+  /// Warning code for a semicolon with no instruction before it, for example `inc var;;`
+  EmptyStatement,
+  /// Error code for trying to change a constant, for example
+  /// `const banana int = 12; inc banana;`
+  ModifiedConstant,
+  /// Error code for a keyword used as a name, for example `let loop int = 1;`
+  KeywordAsName,
+  /// Warning code for code that can never run, for example
+  /// `loop outer { break outer; inc var; }`
+  UnreachableCode,
+}
+
+/// How serious an error is.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+  /// Compilation cannot go on. Counted in `ERROR_COUNT`.
+  Error,
+  /// Probably a mistake, but compilation can go on. Counted in `WARNING_COUNT`.
+  Warning,
+}
+
+impl ErrorCode {
+  /// Whether this kind of error is an actual error or only a warning.
+  pub fn severity(&self) -> Severity {
+    match self {
+      ErrorCode::ForgotSemicolon
+      | ErrorCode::InvalidFileWarning
+      | ErrorCode::EmptyStatement
+      | ErrorCode::UnreachableCode => Severity::Warning,
+      _ => Severity::Error,
+    }
+  }
 }
 
 /// This function only prints the errors and does not cause exiting the program.
@@ -33,39 +127,55 @@ pub enum ErrorCode {
 ///
 pub fn print_error(error_code: ErrorCode, token: Token, extra_info: &str) {
   
-  let mut is_warning: bool = false;   // If an error is only a warning, this will be set to
-                                      // true and the error count will not be incremented.
+  // Warnings are shown in a different colour, and are not counted as errors.
+  let severity: Severity = error_code.severity();
 
-  let mut colored_extra_info = extra_info.red();
+  // Past the limit, only count it.
+  if let Some(max_errors) = unsafe { MAX_ERRORS } {
+    if unsafe { ERROR_COUNT + WARNING_COUNT } >= max_errors {
+      unsafe {
+        match severity {
+          Severity::Warning => WARNING_COUNT += 1,
+          Severity::Error => ERROR_COUNT += 1,
+        }
+      }
+      return;
+    }
+  }
+
+  let mut extra_info: &str = extra_info;
   if extra_info == "" {
-    colored_extra_info = "none".red();
+    extra_info = "none";
   }
+  let colored_extra_info = match severity {
+    Severity::Error => extra_info.red(),
+    Severity::Warning => extra_info.custom_color(WARNING_COLOUR),
+  };
 
   println!();
   match error_code {
     ErrorCode::UnknownKeyword => {
-      println!("Unkown token {} at line {}", token.token.italic(), (token.line+1).to_string().blue());
+      println!("Unkown token {} at line {}", token.text().italic(), (token.line+1).to_string().blue());
       show_lines(token);
       println!("Additional information: {}", colored_extra_info);
     }
     ErrorCode::IncorrectTypeValuePassed => {
-      println!("Incorrect type of value assigned to \"{}\" at line {}", token.token.italic(), (token.line+1).to_string().blue());
+      println!("Incorrect type of value assigned to \"{}\" at line {}", token.text().italic(), (token.line+1).to_string().blue());
       show_lines(token);
       println!("Additional information: {}", colored_extra_info);
     }
     ErrorCode::LackingParameters => {
-      println!("Incorrect amount of parameters for {} at line {}", token.token.italic(), (token.line+1).to_string().blue());
+      println!("Incorrect amount of parameters for {} at line {}", token.text().italic(), (token.line+1).to_string().blue());
       show_lines(token);
-      println!("Additional information: {} {}", "Fatal error, IR generation cannot proceed, further errors will not be reported.".red(), colored_extra_info);
+      println!("Additional information: {}", colored_extra_info);
     }
     ErrorCode::ForgotSemicolon => {
       println!("You might have forgotten a semicolon at line {}", (token.line+1).to_string().blue());
       show_lines(token);
-      println!("Additional information: {}", colored_extra_info.custom_color(WARNING_COLOUR));
-      is_warning = true;
+      println!("Additional information: {}", colored_extra_info);
     }
     ErrorCode::VariableNotDefined => {
-      println!("Variable {} referenced before assignment at line {}", token.token.italic(), (token.line+1).to_string().blue());
+      println!("Variable {} referenced before assignment at line {}", token.text().italic(), (token.line+1).to_string().blue());
       show_lines(token);
       println!("Additional information: {}", colored_extra_info);
     }
@@ -75,11 +185,40 @@ pub fn print_error(error_code: ErrorCode, token: Token, extra_info: &str) {
     }
     ErrorCode::InvalidFileWarning => {
       println!("Potentially invalid file.");
-      println!("Additional information: {}", colored_extra_info.custom_color(WARNING_COLOUR));
-      is_warning = true;
+      println!("Additional information: {}", colored_extra_info);
     }
     ErrorCode::CannotFindCounterpart => {
-      println!("Token {} on line {} has no matching counterpart.", token.token.italic(), (token.line+1).to_string().blue());
+      println!("Token {} on line {} has no matching counterpart.", token.text().italic(), (token.line+1).to_string().blue());
+      show_lines(token);
+      println!("Additional information: {}", colored_extra_info);
+    }
+    ErrorCode::UnterminatedString => {
+      println!("String {} on line {} is never closed.", token.text().italic(), (token.line+1).to_string().blue());
+      show_lines(token);
+      println!("Additional information: {}", colored_extra_info);
+    }
+    ErrorCode::InvisibleCharacter => {
+      println!("Invisible character on line {}", (token.line+1).to_string().blue());
+      show_lines(token);
+      println!("Additional information: {}", colored_extra_info);
+    }
+    ErrorCode::EmptyStatement => {
+      println!("Empty instruction at line {}, you might have written one semicolon too many", (token.line+1).to_string().blue());
+      show_lines(token);
+      println!("Additional information: {}", colored_extra_info);
+    }
+    ErrorCode::ModifiedConstant => {
+      println!("Constant {} cannot be changed at line {}", token.text().italic(), (token.line+1).to_string().blue());
+      show_lines(token);
+      println!("Additional information: {}", colored_extra_info);
+    }
+    ErrorCode::KeywordAsName => {
+      println!("Keyword {} cannot be used as a name at line {}", token.text().italic(), (token.line+1).to_string().blue());
+      show_lines(token);
+      println!("Additional information: {}", colored_extra_info);
+    }
+    ErrorCode::UnreachableCode => {
+      println!("Unreachable code at line {}", (token.line+1).to_string().blue());
       show_lines(token);
       println!("Additional information: {}", colored_extra_info);
     }
@@ -87,11 +226,9 @@ pub fn print_error(error_code: ErrorCode, token: Token, extra_info: &str) {
   println!();
 
   unsafe {
-    if is_warning {
-      WARNING_COUNT += 1;
-    }
-    else {
-      ERROR_COUNT += 1;
+    match severity {
+      Severity::Warning => WARNING_COUNT += 1,
+      Severity::Error => ERROR_COUNT += 1,
     }
   }
 }
@@ -103,6 +240,12 @@ pub fn print_error(error_code: ErrorCode, token: Token, extra_info: &str) {
 /// have been printed by `print_error()`
 ///
 pub fn throw_errors() {
+  if let Some(max_errors) = unsafe { MAX_ERRORS } {
+    let total: usize = unsafe { ERROR_COUNT + WARNING_COUNT };
+    if total > max_errors {
+      println!("... and {} more\n", total - max_errors);
+    }
+  }
   unsafe { println!("{} {}", "Warnings:".custom_color(WARNING_COLOUR), WARNING_COUNT.to_string().custom_color(WARNING_COLOUR)); }
   unsafe { println!("{} {}", "Errors occurred:".red(), ERROR_COUNT.to_string().red()); }
   println!();
@@ -110,13 +253,16 @@ pub fn throw_errors() {
     if ERROR_COUNT > 0 {
       process::exit(1);
     }
+    else if CHECK_ONLY {
+      println!("No issues found with program.")
+    }
     else {
       println!("No issues found with program. Starting compilation...")
     }
   }
 }
 
-/// Show the lines around the problematic one
+/// Show the lines around the problematic one, `CONTEXT_LINES` before and after it
 /// ```
 ///  9 |
 /// 10 | Something problematic here
@@ -125,45 +271,27 @@ pub fn throw_errors() {
 /// With nice colours too
 fn show_lines(token: Token) -> () {
 
-  // get the file path
-  let args: Vec<String> = env::args().collect();
-  let file_path = &args[FILEPATH_ARG_INDEX];
-  
-  let line: usize = token.line;
-
-  // Create a Vector for each line of the code.
-  let mut lines = Vec::new();
-  // Iterate through the lines, add to Vector
-  lines.push(String::from(" "));
-  for line in read_to_string(file_path).unwrap().lines() {
-    lines.push(line.to_string());
+  let source_file: &SourceFile = match SOURCE_FILE.get() {
+    Some(source_file) => source_file,
+    None => return,
+  };
+  if source_file.line_count() == 0 {
+    return;
   }
-  lines.push(String::from(" "));
-  let line = line+1;
 
-  // Look at the digit count of each line and add a space in front accordingly
-  // example:
-  // "9", "10", "11" => " 9", "10", "11"
+  // Don't go past the start or the end of the file.
+  let context_lines: usize = unsafe { CONTEXT_LINES };
+  let first_line: usize = token.line.saturating_sub(context_lines);
+  let last_line: usize = token.line.saturating_add(context_lines).min(source_file.line_count() - 1);
+
+  // Line numbers are aligned to the right, so every `|` lines up:
   //  9 |   <=== the extra space is to align everything
   // 10 |
   // 11 |
-  let digit_count_first: u32 = (line-1).checked_ilog10().unwrap_or(0) + 1;
-  let digit_count_middle: u32 = (line).checked_ilog10().unwrap_or(0) + 1;
-  let digit_count_last: u32 = (line+1).checked_ilog10().unwrap_or(0) + 1;
+  let line_number_width: usize = (last_line + 1).to_string().len();
 
-  let mut line_1: String = (line-1).to_string();
-  let mut line_2: String = (line).to_string();
-  let line_3: String = (line+1).to_string();
-  
-  if digit_count_first < digit_count_middle {
-    line_1 = " ".to_owned() + &(line-1).to_string();
+  for line in first_line..=last_line {
+    let line_number: String = format!("{:>width$}", line + 1, width = line_number_width);
+    println!("{} {} {}", line_number.blue(), "|".blue(), source_file.line_text(line).unwrap_or(""));
   }
-  if digit_count_middle < digit_count_last {
-    line_1 = " ".to_owned() + &(line-1).to_string();
-    line_2 = " ".to_owned() + &(line).to_string();
-  }
-
-  println!("{} {} {}", line_1.blue(), "|".blue(), lines[line-1]);
-  println!("{} {} {}", line_2.blue(), "|".blue(), lines[line]);
-  println!("{} {} {}", line_3.blue(), "|".blue(), lines[line+1]);
-}
\ No newline at end of file
+}
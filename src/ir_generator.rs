@@ -1,4 +1,3 @@
-use core::panic;
 use std::vec;
 use std::iter::FromIterator;
 
@@ -19,7 +18,7 @@ fn var_type(token: Token) -> Type {
 
   unsafe {
     for variable in VARIABLE_LIST.clone() {
-      if token.token == variable.name {
+      if token.text() == variable.name {
         return variable.var_type;
       }
     }
@@ -27,7 +26,8 @@ fn var_type(token: Token) -> Type {
     // step where all variables are found beforehand.
     error::print_error(error::ErrorCode::VariableNotDefined, token, "")
   }
-  panic!("DEV: tried to check variable type of variable that does not exist.");
+  // The error has been reported, let the caller skip over this variable.
+  return Type::None;
 }
 
 /// A preliminary function that finds all variables and populates the variable list
@@ -121,6 +121,9 @@ pub fn generate_ir(tokens: Vec<Token>) -> Vec<Instruction> {
   // Generate the IR by calling the second create_instructions function
   // the function is defined below and can call itself recursively.
   let ir: Vec<Instruction> = create_instructions(tokens);
+
+  // Remove the instructions that can never run.
+  let ir: Vec<Instruction> = eliminate_dead_code(ir);
   
   // cause panic if there were any errors during IR generations
   error::throw_errors();
@@ -148,6 +151,15 @@ pub fn generate_ir(tokens: Vec<Token>) -> Vec<Instruction> {
       // - Delete everything before the semicolon
       // - Repeat until we ate and no crumbs are left
 
+      // An empty statement, a `;` with nothing before it. There is nothing to do,
+      // but it is probably a typo so warn about it.
+      // EXAMPLE: inc var;;
+      if !tokens_to_process.is_empty() && tokens_to_process[0].text() == ";" {
+        error::print_error(error::ErrorCode::EmptyStatement, tokens_to_process[0].clone(), "");
+        tokens_to_process.remove(0);
+        continue;
+      }
+
       let mut index_of_semicolon: usize = index_first_occurence_of(tokens_to_process.clone(), String::from(";"));
 
       if index_of_semicolon == 0 {
@@ -167,6 +179,19 @@ pub fn generate_ir(tokens: Vec<Token>) -> Vec<Instruction> {
           // Increment an integer. A very simple instruction.
 
           let variable_to_increment: String = tokens_to_process[1].token.clone();
+
+          // Constants can't be changed, only variables made with `let` can.
+          // (`var_type` reports variables that don't exist.)
+          match var_type(tokens_to_process[1].clone()) {
+            Type::ConstInt(_) | Type::ConstStr(_) => {
+              error::print_error(
+                error::ErrorCode::ModifiedConstant,
+                tokens_to_process[1].clone(),
+                "Use `let` instead of `const` to create a variable that can be changed."
+              );
+            }
+            _ => {}
+          }
           
           let instruction: Instruction = Instruction {
             inst_type: Type::Increment(variable_to_increment),
@@ -219,6 +244,17 @@ pub fn generate_ir(tokens: Vec<Token>) -> Vec<Instruction> {
           };
 
           instructions_to_return.push(instruction);
+
+          // Anything after the break in the same loop can never run. It is removed
+          // later on by `eliminate_dead_code`, but it is probably a mistake so warn about it.
+          // EXAMPLE: break loop_name; println(var);
+          if tokens_to_process.len() > index_of_semicolon+1 {
+            error::print_error(
+              error::ErrorCode::UnreachableCode,
+              tokens_to_process[index_of_semicolon+1].clone(),
+              "This comes after a break, so it never runs."
+            );
+          }
         }
 
         // MARK: Let | Const
@@ -246,13 +282,25 @@ pub fn generate_ir(tokens: Vec<Token>) -> Vec<Instruction> {
               tokens_to_process[0].clone(),
               "Let bindings work like this: let variable_name data_type = value;"
             );
-            break;
+            skip_statement(&mut tokens_to_process, index_of_semicolon);
+            continue;
           }
 
           // tokens[1] (the second token) is the variable name.
           // We store it for later use.
           let varname: String = tokens_to_process[1].token.clone();
 
+          // A keyword can't be a name, `let int int = 1;` would be confusing at best.
+          if tokens_to_process[1].is_keyword() {
+            error::print_error(
+              error::ErrorCode::KeywordAsName,
+              tokens_to_process[1].clone(),
+              "Pick a different name."
+            );
+            skip_statement(&mut tokens_to_process, index_of_semicolon);
+            continue;
+          }
+
           // find where the (first) `=` is located, because we know everyhing after that is (a) value(s)
           let index_of_equal: usize = index_first_occurence_of(tokens_to_process.clone(), String::from("="));
 
@@ -296,19 +344,23 @@ pub fn generate_ir(tokens: Vec<Token>) -> Vec<Instruction> {
               // I don't remember how this works because I didn't document it whenever I made it,
               // like 3 months ago as of this comment.
               let instruction: Instruction = Instruction {
-                inst_type: match tokens_to_process[index_of_equal + 1].token.clone().as_str().parse::<u64>() {
-                  Ok(value) => int_type(value, is_constant), // int_type returns Type::ConstInt if constant, Type::Int otherwise.
-                  Err(_) => {
+                inst_type: match tokens_to_process[index_of_equal + 1].numeric_value() {
+                  Some(value) => int_type(value, is_constant), // int_type returns Type::ConstInt if constant, Type::Int otherwise.
+                  None => {
+                    let value_token: &Token = &tokens_to_process[index_of_equal + 1];
+                    let mut error_message: String = format!("Value passed was not an unsigned 64 bit integer. (0-{})", u64::MAX);
+                    if value_token.is_integer_literal() {
+                      error_message = format!("{} is too big for an unsigned 64 bit integer. (0-{})", value_token.text(), u64::MAX);
+                    }
                     error::print_error(
                       error::ErrorCode::IncorrectTypeValuePassed,
                       tokens_to_process[2].clone(),
-                      "Value passed was not an unsigned 64 bit integer. (0-18446744073709551615)"
+                      &error_message
                     );
-                    // Return something random because we caused an error anyways
-                    return vec![Instruction {
-                      inst_type: Type::Int(0),
-                      parameters: Vec::new(),
-                    }];
+                    // Skip this statement and keep going, so the errors after it
+                    // also get reported.
+                    skip_statement(&mut tokens_to_process, index_of_semicolon);
+                    continue;
                   }
                 },
                 parameters: vec![Instruction {
@@ -373,6 +425,11 @@ pub fn generate_ir(tokens: Vec<Token>) -> Vec<Instruction> {
 
           for varname_index in index_of_open_bracket+1..index_of_closed_bracket {
 
+            // The variables can also be separated by commas: println(var, var2)
+            if tokens_to_process[varname_index].text() == "," {
+              continue;
+            }
+
             let varname = tokens_to_process[varname_index].token.clone();
             let var_type = var_type(tokens_to_process[varname_index].clone());
 
@@ -414,6 +471,10 @@ pub fn generate_ir(tokens: Vec<Token>) -> Vec<Instruction> {
                 instructions_to_return.push(instruction);
               }
 
+              Type::None => {
+                // Variable is not defined. The error was already printed by `var_type`.
+              }
+
               _ => {
                 todo!("DEV: Tried to print non-implemented data type");
               }
@@ -440,14 +501,91 @@ pub fn generate_ir(tokens: Vec<Token>) -> Vec<Instruction> {
       
       // end of loop here
       // delete all tokens before semicolon
-      for _ in 0..index_of_semicolon+1 {
-        tokens_to_process.remove(0);
-      }
+      skip_statement(&mut tokens_to_process, index_of_semicolon);
     }
     return instructions_to_return
   }
 }
 
+/// Delete every token up to and including the semicolon at `index_of_semicolon`.
+///
+/// Used at the end of each statement, but also to recover from an error: the broken
+/// statement is skipped and IR generation carries on with the next one, so all the
+/// errors in the code get reported at once instead of just the first.
+fn skip_statement(tokens: &mut Vec<Token>, index_of_semicolon: usize) {
+  for _ in 0..index_of_semicolon+1 {
+    tokens.remove(0);
+  }
+}
+
+/// Removes the instructions that can never run: everything that comes after a `break`,
+/// or after a loop that is never broken out of, in the same block.
+/// ```
+/// loop example {
+///   break example;
+///   println(hello);  // removed
+/// }
+/// ```
+/// Variable definitions are kept even if they never run, because the rest of the code
+/// can still refer to them.
+fn eliminate_dead_code(instructions: Vec<Instruction>) -> Vec<Instruction> {
+  let mut live_instructions: Vec<Instruction> = Vec::new();
+  let mut is_reachable: bool = true;
+
+  for mut instruction in instructions {
+    if !is_reachable {
+      live_instructions.append(&mut definitions_in(instruction));
+      continue;
+    }
+
+    if let Type::Loop(_) = instruction.inst_type {
+      instruction.parameters = eliminate_dead_code(instruction.parameters);
+    }
+
+    // Check whether the program can carry on to the next instruction after this one.
+    match &instruction.inst_type {
+      Type::LoopExit(_) => {
+        is_reachable = false;
+      }
+      Type::Loop(loop_name) if !breaks_out_of(&instruction.parameters, loop_name) => {
+        is_reachable = false;
+      }
+      _ => {}
+    }
+    live_instructions.push(instruction);
+  }
+  return live_instructions;
+}
+
+/// Returns the variable definitions in an instruction, looking inside of loops too.
+/// Used by `eliminate_dead_code`.
+fn definitions_in(instruction: Instruction) -> Vec<Instruction> {
+  match instruction.inst_type {
+    Type::Int(_) | Type::ConstInt(_) | Type::Str(_) | Type::ConstStr(_) => {
+      return vec![instruction];
+    }
+    Type::Loop(_) => {
+      return instruction.parameters.into_iter().flat_map(definitions_in).collect();
+    }
+    _ => {
+      return Vec::new();
+    }
+  }
+}
+
+/// Whether there is a `break loop_name` in `instructions`, including inside of nested loops.
+/// Used by `eliminate_dead_code`.
+fn breaks_out_of(instructions: &[Instruction], loop_name: &str) -> bool {
+  for instruction in instructions {
+    match &instruction.inst_type {
+      Type::LoopExit(name) if name == loop_name => return true,
+      Type::Loop(_) if breaks_out_of(&instruction.parameters, loop_name) => return true,
+      _ => {}
+    }
+  }
+  return false;
+}
+
 /// Get the index of the curly brace that closes the block the start of `tokens` is in.
 /// For example, if we had the following in `Vec<Token>` form:
 /// ```
@@ -464,10 +602,10 @@ fn closing_curly_brace_index(tokens: Vec<Token>) -> usize {
   let mut open_brace_counter: usize = 0;
 
   for (index_of_token, token) in tokens.iter().enumerate() {
-    if token.token.as_str() == "{" {
+    if token.text() == "{" {
       open_brace_counter += 1;
     }
-    if token.token.as_str() == "}" {
+    if token.text() == "}" {
       open_brace_counter -= 1;
       if open_brace_counter == 0 {
         return index_of_token
@@ -489,7 +627,7 @@ fn closing_curly_brace_index(tokens: Vec<Token>) -> usize {
 fn index_first_occurence_of(tokens: Vec<Token>, query: String) -> usize {
   let mut index_of_occurence: usize = 0;
   for (index, token) in tokens.clone().iter().enumerate() {
-    if token.token == query {
+    if token.text() == query {
       index_of_occurence = index;
       break
     }
@@ -559,4 +697,69 @@ pub enum Type {
   PrintLn,
   /// placeholder
   None,
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// An instruction with no parameters.
+  fn instruction(inst_type: Type) -> Instruction {
+    Instruction { inst_type, parameters: Vec::new() }
+  }
+
+  fn loop_instruction(loop_name: &str, parameters: Vec<Instruction>) -> Instruction {
+    Instruction { inst_type: Type::Loop(loop_name.to_string()), parameters }
+  }
+
+  /// The type of every instruction, to compare them easily.
+  fn types(instructions: &[Instruction]) -> Vec<Type> {
+    instructions.iter().map(|instruction| instruction.inst_type.clone()).collect()
+  }
+
+  #[test]
+  fn code_after_break_is_removed() {
+    let ir = vec![loop_instruction("a", vec![
+      instruction(Type::PrintLn),
+      instruction(Type::LoopExit("a".to_string())),
+      instruction(Type::Increment("var".to_string())),
+    ])];
+    let ir = eliminate_dead_code(ir);
+    assert_eq!(types(&ir[0].parameters), [Type::PrintLn, Type::LoopExit("a".to_string())]);
+  }
+
+  #[test]
+  fn code_after_endless_loop_is_removed() {
+    let ir = vec![
+      loop_instruction("a", vec![instruction(Type::PrintLn)]),
+      instruction(Type::Increment("var".to_string())),
+    ];
+    assert_eq!(types(&eliminate_dead_code(ir)), [Type::Loop("a".to_string())]);
+  }
+
+  #[test]
+  fn code_after_loop_with_break_is_kept() {
+    // The break is in an inner loop, but still exits the outer one.
+    let ir = vec![
+      loop_instruction("outer", vec![
+        loop_instruction("inner", vec![instruction(Type::LoopExit("outer".to_string()))]),
+      ]),
+      instruction(Type::PrintLn),
+    ];
+    assert_eq!(types(&eliminate_dead_code(ir)), [Type::Loop("outer".to_string()), Type::PrintLn]);
+  }
+
+  #[test]
+  fn unreachable_definitions_are_kept() {
+    let ir = vec![
+      loop_instruction("a", vec![instruction(Type::PrintLn)]),
+      instruction(Type::Int(1)),
+      loop_instruction("b", vec![instruction(Type::ConstStr("hi".to_string()))]),
+      instruction(Type::PrintLn),
+    ];
+    assert_eq!(
+      types(&eliminate_dead_code(ir)),
+      [Type::Loop("a".to_string()), Type::Int(1), Type::ConstStr("hi".to_string())]
+    );
+  }
+}
@@ -2,7 +2,7 @@ use std::process;
 
 use std::fs;
 
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 // Colours in the terminal
 use colored::*;
@@ -37,6 +37,10 @@ mod error;
 fn main() {
   // get info from the command line arguments
   let parameters: cli::Parameters = cli::handle_args();
+  error::set_max_errors(parameters.max_errors);
+  error::set_context_lines(parameters.context_lines);
+  // Nothing is compiled when only checking or showing a step's output.
+  error::set_check_only(parameters.check_only || parameters.emit.is_some());
 
   println!("{} {}\n", "Kathleen: compiling".green(), parameters.file_path.green().italic());
 
@@ -51,24 +55,69 @@ fn main() {
   for line in fs::read_to_string(parameters.file_path).unwrap().lines() {
     code_lines.push(line.to_string());
   }
+  // Errors show the lines around them, keep them for that.
+  error::set_source_file(code_lines.clone());
 
   // Compile
 
+  // How long each step took, shown with the `time` option.
+  let mut phase_times: Vec<(&str, Duration)> = Vec::new();
+
   // Tokenize the lines of code
+  let phase_start_time = Instant::now();
   let tokens
-    = tokenizer::tokenize(code_lines);
+    = tokenizer::tokenize_with_defines(code_lines, &parameters.defines);
+  phase_times.push(("Tokenization", phase_start_time.elapsed()));
+
+  if parameters.emit == Some(cli::EmitStage::Tokens) {
+    for token in &tokens {
+      println!("{}:{}\t{}", token.line+1, token.token_number, token.text());
+    }
+    // The IR generator usually does this, exit with an error if tokenizing went wrong.
+    error::throw_errors();
+    return;
+  }
 
   // Generate the intermediate representation out of the tokens
+  let phase_start_time = Instant::now();
   let intermediate_representation
     = ir_generator::generate_ir(tokens);
+  phase_times.push(("IR generation", phase_start_time.elapsed()));
+
+  if parameters.emit == Some(cli::EmitStage::Ir) {
+    println!("{:#?}", intermediate_representation);
+    return;
+  }
+
+  // All errors have been found (and caused an exit) by now,
+  // so a check is done at this point.
+  if parameters.check_only {
+    if parameters.show_phase_times {
+      print_phase_times(&phase_times);
+    }
+    return;
+  }
 
   // Generate the near assembly representation using the intermediate representation.
+  let phase_start_time = Instant::now();
   let near_assembly_representation
     = nar_generator::generate_nar(intermediate_representation);
+  phase_times.push(("NAR generation", phase_start_time.elapsed()));
+
+  if parameters.emit == Some(cli::EmitStage::Nar) {
+    println!("{:#?}", near_assembly_representation);
+    return;
+  }
 
   // Generate the assembly output using the NAR
+  let phase_start_time = Instant::now();
   let assembly_output
     = asm_generator::generate_asm(near_assembly_representation);
+  phase_times.push(("Assembly generation", phase_start_time.elapsed()));
+
+  if parameters.show_phase_times {
+    print_phase_times(&phase_times);
+  }
 
   // The following could totally be done in a different module. Not sure if useful.
 
@@ -142,3 +191,13 @@ fn main() {
     fs::remove_file(obj_path).expect("DEV: Couldn't remove out path");
   }
 }
+
+/// Shows how long each step of the compilation took, to find
+/// out which one is slow.
+fn print_phase_times(phase_times: &[(&str, Duration)]) {
+  println!("{}", "Time spent in each step:".green());
+  for (phase, duration) in phase_times {
+    println!("    {}: {:?}", phase, duration);
+  }
+  println!();
+}
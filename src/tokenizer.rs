@@ -1,4 +1,80 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+
+use crate::error; // For throwing errors.
+
+/// The special characters that the tokenizer separates from the rest.
+/// A constant rather than a `HashSet`, so nothing has to be built every time the tokenizer runs.
+const SPECIAL_CHARS: [char; 21] = [ // for clarity:
+  '(', ')',                         // brackets
+  '{', '}',                         // curly brackets
+  '[', ']',                         // square brackets
+  '<', '>',                         // smaller and greater signs
+  '!', '|', '&',                    // exclamation mark, or operator, and operator
+  ',', '.', ':', ';',               // comma, period, colon, semicolon
+  '+', '*', '/', '-', '=', '^',     // mathematical operators: plus, multiplication,
+                                    // division, minus, equals, power
+];
+
+/// Every keyword of the language: instructions and data types.
+/// They can't be used as names.
+pub const KEYWORDS: [&str; 9] = [
+  "let", "const",           // variable definitions
+  "int", "str",             // data types
+  "print", "println",       // output
+  "inc",                    // maths
+  "loop", "break",          // logic
+];
+
+/// Whether a word is one of the `KEYWORDS`.
+pub fn is_keyword(word: &str) -> bool {
+  return KEYWORDS.contains(&word);
+}
+
+/// Invisible characters that are almost always pasted by accident, and would otherwise
+/// end up silently glued to a token. They are only allowed inside strings.
+const INVISIBLE_CHARS: [char; 6] = [
+  '\u{00A0}',  // no-break space
+  '\u{200B}',  // zero width space
+  '\u{200C}',  // zero width non-joiner
+  '\u{200D}',  // zero width joiner
+  '\u{2060}',  // word joiner
+  '\u{FEFF}',  // byte order mark (zero width no-break space)
+];
+
+/// The different kinds of characters, as far as separating tokens is concerned.
+/// See `classify_char`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CharClass {
+  /// `"`, starts or ends a string.
+  Quote,
+  /// Separates tokens and is thrown away.
+  Whitespace,
+  /// One of `INVISIBLE_CHARS`. Separates tokens like whitespace, but is an error.
+  Invisible,
+  /// One of `SPECIAL_CHARS`. Separates tokens and is a token of its own.
+  Special,
+  /// Part of a normal word.
+  Normal,
+}
+
+/// Tells what kind of character `ch` is, outside of a string.
+fn classify_char(ch: char) -> CharClass {
+  if ch == '"' {
+    return CharClass::Quote;
+  }
+  // Checked before whitespace, because some invisible characters (no-break space)
+  // also count as whitespace.
+  if INVISIBLE_CHARS.contains(&ch) {
+    return CharClass::Invisible;
+  }
+  if ch.is_whitespace() {
+    return CharClass::Whitespace;
+  }
+  if SPECIAL_CHARS.contains(&ch) {
+    return CharClass::Special;
+  }
+  return CharClass::Normal;
+}
 
 /// This is the tokeniser and the pre-processor
 /// 
@@ -8,7 +84,7 @@ use std::collections::HashSet;
 /// (everything between two `"`)
 /// - It appends every line (Which is now a vector of tokens) to another vector.
 ///   - The index of the line in this vector is also its line number - 1.
-/// - It removes all comments
+/// - It removes all comments, without mistaking a `//` inside a string for one.
 /// - It returns a Vector of `Token` structs.
 /// 
 /// ```rust
@@ -20,23 +96,14 @@ use std::collections::HashSet;
 /// ```
 pub fn tokenize(lines: Vec<String>) -> Vec<Token> {
 
-  let mut tokenised_lines: Vec<Vec<String>> = Vec::new();
+  let mut tokenised_lines: Vec<Vec<String>> = Vec::with_capacity(lines.len());
 
   // SEPARATE TOKENS BY WHITESPACE AND SPECIAL CHARACTERS.
 
-  // A set of special characters to separate
-  let special_chars: HashSet<char> = [// for clarity:
-    '(', ')',                         // brackets
-    '{', '}',                         // curly brackets
-    '[', ']',                         // square brackets
-    '<', '>',                         // smaller and greater signs
-    '!', '|', '&',                    // exclamation mark, or operator, and operator
-    ',', '.', ':', ';',               // comma, period, colon, semicolon
-    '+', '*', '/', '-', '=', '^',     // mathematical operators: plus, multiplication, 
-                                      // division, minus, equals, power
-  ].iter().cloned().collect();
-
-  for line in lines {
+  // Holds a string that is continued on the next line (see the end of the loop).
+  let mut continued_string: Option<String> = None;
+
+  for (line_number, line) in lines.into_iter().enumerate() {
     // Vector to hold the tokens of the current line
     let mut tokens = Vec::new();
     // String to hold the current token
@@ -44,50 +111,134 @@ pub fn tokenize(lines: Vec<String>) -> Vec<Token> {
 
     let mut is_string: bool = false;
 
+    // Whether the last token pushed to `tokens` is a `/` special character
+    // (not a string that happens to be "/"), used to find comments.
+    let mut last_token_is_slash: bool = false;
+
+    // Whether the current token is a string that was just closed, and whether the
+    // last token pushed to `tokens` was one. Used to join strings that follow eachother.
+    let mut token_is_string: bool = false;
+    let mut last_token_is_string: bool = false;
+
+    // If the previous line ended in the middle of a string, pick it up where
+    // it was left, ignoring the indentation of this line.
+    let mut line_content: &str = &line;
+    if let Some(string) = continued_string.take() {
+      token = string;
+      is_string = true;
+      line_content = line.trim_start();
+    }
+
+    // A byte order mark at the very start of the file is fine, some editors add one.
+    // So is a shebang (`#!/usr/bin/env ...`) as the first line, it is skipped entirely.
+    if line_number == 0 {
+      line_content = line_content.strip_prefix('\u{FEFF}').unwrap_or(line_content);
+      if line_content.starts_with("#!") {
+        line_content = "";
+      }
+    }
+
     // Iterate through every character
-    for ch in line.chars() {
+    for ch in line_content.chars() {
 
-      // " Marks either the end or the start of a string. If this character appears,
-      // it is to be ignored and is_string variable gets inverted.
-      if ch == '"' {
-        is_string = !is_string;
+      // If we are dealing with a string, push the character no matter
+      // what, unless it is the `"` that closes the string.
+      if is_string && ch != '"' {
+        token.push(ch);
+        continue;
       }
-      else {
-        // If we are not dealing with a string, standard separation
-        // logic applies.
-        if !is_string {
-          // If that character is a space, add the token variable
-          // the tokens vector and clear the token variable
-          if ch.is_whitespace() {
-            if !token.is_empty() { // Sometimes there was nothing here before
-              tokens.push(token);
-            }
-            token = String::new();
+
+      // Otherwise, standard separation logic applies.
+      let char_class: CharClass = classify_char(ch);
+      match char_class {
+
+        // " Marks either the end or the start of a string. If this character appears,
+        // it is to be ignored and is_string variable gets inverted.
+        CharClass::Quote => {
+          // Two strings separated only by whitespace are joined into one:
+          // "Hello " "World!" => Hello World!
+          if !is_string && token.is_empty() && last_token_is_string {
+            token = tokens.pop().unwrap_or_default();
+          }
+          is_string = !is_string;
+          token_is_string = !is_string;
+        }
+
+        // If that character is a space, add the token variable
+        // the tokens vector and clear the token variable.
+        // Invisible characters are also treated as spaces, so they don't end up in a token.
+        CharClass::Whitespace | CharClass::Invisible => {
+          if char_class == CharClass::Invisible {
+            error::print_error(
+              error::ErrorCode::InvisibleCharacter,
+              Token::new(&token).line(line_number).token_number(tokens.len()),
+              &format!("Found U+{:04X} here. Delete it, or replace it with a normal space.", ch as u32)
+            );
           }
-          // If the character is a special token, add the token variable
-          // to the tokens vector, as well as the special character as
-          // another token.
-          else if special_chars.contains(&ch) {
-            if !token.is_empty() {
-              tokens.push(token);
-            }
-            token = String::new();
-            tokens.push(ch.to_string())
+          if !token.is_empty() { // Sometimes there was nothing here before
+            tokens.push(token);
+            last_token_is_slash = false;
+            last_token_is_string = token_is_string;
           }
-          // Otherwise, it is just a normal character part of a normal word,
-          // so just push it to the token variable.
-          else {
-            token.push(ch);
+          token = String::new();
+        }
+
+        // If the character is a special token, add the token variable
+        // to the tokens vector, as well as the special character as
+        // another token.
+        CharClass::Special => {
+          if !token.is_empty() {
+            tokens.push(token);
+            last_token_is_slash = false;
+          }
+          token = String::new();
+          last_token_is_string = false;
+
+          // REMOVE COMMENTS
+          // Two `/` following eachother, even with whitespace in between, start a
+          // comment: remove the first `/` and ignore the rest of the line. This is
+          // done here rather than after tokenizing, because here we still know
+          // that a "/" string is a string and not the start of a comment.
+          if ch == '/' && last_token_is_slash {
+            tokens.pop();
+            break;
           }
+          last_token_is_slash = ch == '/';
+          tokens.push(ch.to_string())
         }
-        // If we are indeed dealing with a string, push the character
-        // no matter what.
-        else {
+
+        // Otherwise, it is just a normal character part of a normal word,
+        // so just push it to the token variable.
+        CharClass::Normal => {
           token.push(ch);
+          token_is_string = false;
         }
       }
     }
-      
+
+    // A `\` at the very end of a line, inside a string, continues the string on the
+    // next line. Neither the `\`, the newline nor the next line's indentation are
+    // part of the string:
+    // "abc\
+    //   def"    => abcdef
+    if is_string && token.ends_with('\\') {
+      token.pop();
+      continued_string = Some(token);
+      tokenised_lines.push(tokens);
+      continue;
+    }
+
+    // A string has to be closed on the line it is on. Report it and
+    // carry on as if it was closed at the end of the line.
+    if is_string {
+      error::print_error(
+        error::ErrorCode::UnterminatedString,
+        Token::new(&token).line(line_number).token_number(tokens.len()),
+        "Strings must be closed with `\"` on the line they start. A string can be continued \
+        on the next line by ending the line with `\\`."
+      );
+    }
+
     // If the last token is not empty, push it to the tokens vector
     if !token.is_empty() {
       tokens.push(token);
@@ -98,21 +249,16 @@ pub fn tokenize(lines: Vec<String>) -> Vec<Token> {
     tokenised_lines.push(tokens);
   }
 
-  // REMOVE COMMENTS
-
-  // Run through the lines.
-  // If two "/" tokens are found consecutively, delete them as well as
-  // the rest of the line.
-  for line_index in 0..tokenised_lines.len() {
-    let line = &mut tokenised_lines[line_index];
-
-    if line.len() > 1 { // ignore lines shorter than 2 characters
-      for token_index in 0..(line.len() - 1) { // -1 : no need to check last character
-        if line[token_index] == "/" && line[token_index+1] == "/" { // if two consecutive "/"s are found
-          line.truncate(token_index);   // cut off the rest of the line
-          break;  // exit the loop because otherwise we'd be iterating over nothing.
-        }
-      }
+  // If the file ends in a continued string, report it and keep what there is of it.
+  if let Some(string) = continued_string {
+    let last_line_number: usize = tokenised_lines.len() - 1;
+    if let Some(last_line) = tokenised_lines.last_mut() {
+      error::print_error(
+        error::ErrorCode::UnterminatedString,
+        Token::new(&string).line(last_line_number).token_number(last_line.len()),
+        "The file ends before this string is closed with `\"`."
+      );
+      last_line.push(string);
     }
   }
 
@@ -120,13 +266,16 @@ pub fn tokenize(lines: Vec<String>) -> Vec<Token> {
   // This struct contains the token itself as a String
   // and other information: what line it's in and
   // its position in that line.
-  let mut tokens: Vec<Token> = Vec::new();
+  // The token Strings are moved rather than copied, so that huge tokens
+  // (like a very long string) never get copied around.
+  let token_count: usize = tokenised_lines.iter().map(|line| line.len()).sum();
+  let mut tokens: Vec<Token> = Vec::with_capacity(token_count);
 
-  for (line_number, line) in tokenised_lines.iter().enumerate() {
-    for (token_number, token) in line.iter().enumerate() {
+  for (line_number, line) in tokenised_lines.into_iter().enumerate() {
+    for (token_number, token) in line.into_iter().enumerate() {
       tokens.push(
         Token {
-          token: token.to_string(),
+          token,
           line: line_number,
           token_number,
       });
@@ -135,10 +284,533 @@ pub fn tokenize(lines: Vec<String>) -> Vec<Token> {
   return tokens;
 }
 
+/// Tokenize the code after running the pre-processor directives through
+/// `preprocess`. `defines` is the set of flags that are defined from the
+/// command line (see `cli::Parameters`).
+pub fn tokenize_with_defines(lines: Vec<String>, defines: &HashSet<String>) -> Vec<Token> {
+  return tokenize(preprocess(lines, defines));
+}
+
+/// Handles the pre-processor directives.
+///
+/// Conditional compilation:
+/// ```
+/// #if FLAG
+///   // only compiled if FLAG is defined
+/// #else
+///   // only compiled if FLAG is not defined
+/// #endif
+/// ```
+/// `#if` blocks can be nested.
+///
+/// Macros:
+/// ```
+/// #define NAME value
+/// ```
+/// Every following `NAME` word outside of a string is replaced by `value`. A macro also
+/// defines `NAME` as a flag for `#if`. Defining the same macro again replaces its value
+/// from then on. Macros used in `value` are expanded right away, so a macro can never
+/// expand to itself over and over.
+///
+/// Lines that should not be compiled, as well as the directives themselves, are replaced
+/// by empty lines rather than removed, so every other line keeps its line number.
+fn preprocess(lines: Vec<String>, defines: &HashSet<String>) -> Vec<String> {
+
+  let mut processed_lines: Vec<String> = Vec::new();
+
+  // Flags from the command line, and from `#define`s.
+  let mut defines: HashSet<String> = defines.clone();
+  // Macro names and their values.
+  let mut macros: HashMap<String, String> = HashMap::new();
+  // Whether the previous line ended inside of a string that continues on this line.
+  let mut in_string: bool = false;
+
+  // One entry per `#if` we are currently inside of.
+  let mut conditions: Vec<Condition> = Vec::new();
+
+  for (line_number, line) in lines.into_iter().enumerate() {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let directive_token = Token::new(words.first().unwrap_or(&"")).line(line_number);
+    // Only compile the line if every `#if` it is inside of is active.
+    let is_active: bool = conditions.iter().all(|condition| condition.is_active);
+
+    // A line that starts inside of a string continued from the previous line
+    // is never a directive, even if it starts with `#`.
+    let directive: Option<&&str> = if in_string { None } else { words.first() };
+
+    match directive {
+      Some(&"#if") => {
+        if words.len() != 2 {
+          error::print_error(
+            error::ErrorCode::LackingParameters,
+            directive_token.clone(),
+            "Conditional compilation works like this: #if FLAG_NAME"
+          );
+        }
+        let is_defined = words.len() > 1 && defines.contains(words[1]);
+        conditions.push(Condition { if_token: directive_token, is_active: is_defined, has_else: false });
+        processed_lines.push(String::new());
+        continue;
+      }
+      Some(&"#else") => {
+        match conditions.last_mut() {
+          Some(condition) if condition.has_else => error::print_error(
+            error::ErrorCode::CannotFindCounterpart,
+            directive_token,
+            "This #if already has an #else."
+          ),
+          Some(condition) => {
+            condition.is_active = !condition.is_active;
+            condition.has_else = true;
+          }
+          None => error::print_error(
+            error::ErrorCode::CannotFindCounterpart,
+            directive_token,
+            "This #else has no #if to go with it."
+          ),
+        }
+        processed_lines.push(String::new());
+        continue;
+      }
+      Some(&"#endif") => {
+        if conditions.pop().is_none() {
+          error::print_error(
+            error::ErrorCode::CannotFindCounterpart,
+            directive_token,
+            "This #endif has no #if to go with it."
+          );
+        }
+        processed_lines.push(String::new());
+        continue;
+      }
+      Some(&"#define") => {
+        if is_active {
+          // Everything after the name is the value, spaces included.
+          let definition: &str = line.trim()["#define".len()..].trim_start();
+          let (name, value) = definition.split_once(char::is_whitespace).unwrap_or((definition, ""));
+          if name.is_empty() {
+            error::print_error(
+              error::ErrorCode::LackingParameters,
+              directive_token,
+              "Macros work like this: #define NAME value"
+            );
+          }
+          else {
+            let (value, _) = expand_macros(value.trim(), &macros, false);
+            macros.insert(name.to_string(), value);
+            defines.insert(name.to_string());
+          }
+        }
+        processed_lines.push(String::new());
+        continue;
+      }
+      _ => {}
+    }
+
+    if is_active {
+      let (expanded_line, continues_string) = expand_macros(&line, &macros, in_string);
+      in_string = continues_string;
+      processed_lines.push(expanded_line);
+    }
+    else {
+      processed_lines.push(String::new());
+    }
+  }
+
+  for condition in conditions {
+    error::print_error(
+      error::ErrorCode::CannotFindCounterpart,
+      condition.if_token,
+      "This #if has no #endif to go with it."
+    );
+  }
+
+  return processed_lines;
+}
+
+/// An `#if` that `preprocess` is currently inside of.
+struct Condition {
+  /// The `#if` token, for error messages.
+  if_token: Token,
+  /// Whether the lines in the current branch are compiled.
+  is_active: bool,
+  /// Whether an `#else` was already met, there can only be one.
+  has_else: bool,
+}
+
+/// Replaces every word in `line` that is the name of a macro by the macro's value.
+/// Strings are left alone.
+///
+/// `in_string` is whether the line starts inside of a string continued from the previous
+/// line, the returned `bool` is whether the string continues on the next line.
+///
+/// Comments are left alone too. They are found like `tokenize` does: two `/` outside of
+/// a string with only whitespace between them, so both agree on where strings are.
+fn expand_macros(line: &str, macros: &HashMap<String, String>, mut in_string: bool) -> (String, bool) {
+  let mut expanded_line: String = String::with_capacity(line.len());
+  let mut word: String = String::new();
+  // Whether the last character that isn't whitespace is a `/` outside of a string.
+  let mut last_char_is_slash: bool = false;
+
+  for (index, ch) in line.char_indices() {
+    if in_string {
+      if ch == '"' {
+        in_string = false;
+      }
+      expanded_line.push(ch);
+      continue;
+    }
+
+    let char_class: CharClass = classify_char(ch);
+    if char_class == CharClass::Normal {
+      word.push(ch);
+      last_char_is_slash = false;
+      continue;
+    }
+    // The word is over. Replace it if it is a macro.
+    expanded_line += macros.get(&word).unwrap_or(&word);
+    word.clear();
+
+    match char_class {
+      CharClass::Special if ch == '/' => {
+        if last_char_is_slash {
+          // The rest of the line is a comment.
+          expanded_line += &line[index..];
+          return (expanded_line, false);
+        }
+        last_char_is_slash = true;
+      }
+      CharClass::Whitespace | CharClass::Invisible => {}
+      CharClass::Quote => {
+        in_string = true;
+        last_char_is_slash = false;
+      }
+      _ => last_char_is_slash = false,
+    }
+    expanded_line.push(ch);
+  }
+  expanded_line += macros.get(&word).unwrap_or(&word);
+
+  return (expanded_line, in_string && line.ends_with('\\'));
+}
+
 /// A struct used to represent each token in the code.
 #[derive(Debug, Clone)]
 pub struct Token {
   pub token: String,        // the token itself, for example "let"
   pub line: usize,          // which line it is at
   pub token_number: usize,  // which token in the line this is (0st, 1st, 2nd...)
-}
\ No newline at end of file
+}
+impl Token {
+  /// Create a token at line 0, token 0. Use `line` and `token_number`
+  /// to place it somewhere else:
+  /// ```rust
+  /// let token = Token::new("let").line(3).token_number(0);
+  /// ```
+  pub fn new(token: &str) -> Token {
+    return Token {
+      token: token.to_string(),
+      line: 0,
+      token_number: 0,
+    };
+  }
+
+  /// Set which line the token is at.
+  pub fn line(mut self, line: usize) -> Token {
+    self.line = line;
+    return self;
+  }
+
+  /// Set which token in the line this is.
+  pub fn token_number(mut self, token_number: usize) -> Token {
+    self.token_number = token_number;
+    return self;
+  }
+
+  /// The token itself, for example "let". Prefer this over reading the `token` field.
+  pub fn text(&self) -> &str {
+    return &self.token;
+  }
+
+  /// Whether this token is one of the `KEYWORDS`.
+  pub fn is_keyword(&self) -> bool {
+    return is_keyword(self.text());
+  }
+
+  /// Combine this token with the token that follows it into a single token,
+  /// for example `+` and `=` into `+=`. The new token is where this one is.
+  #[allow(dead_code)] // No pass merges tokens yet, the first operators will.
+  pub fn merge(&self, next: &Token) -> Token {
+    return Token::new(&(self.token.clone() + &next.token))
+      .line(self.line)
+      .token_number(self.token_number);
+  }
+
+  /// Whether two tokens are the same token, wherever they are in the code.
+  /// Unlike comparing whole `Token` structs, `line` and `token_number` are ignored.
+  /// Handy in tests, where the position is usually not what is being checked.
+  #[cfg(test)]
+  pub fn same_content(&self, other: &Token) -> bool {
+    return self.token == other.token;
+  }
+
+  /// Parse the token as an unsigned 64 bit integer literal, the only kind of number
+  /// the language has for now.
+  ///
+  /// Accepts decimal (`1000`), hexadecimal (`0xFF`), octal (`0o17`) and binary (`0b1010`)
+  /// literals, with `_` allowed anywhere after the prefix as a digit separator (`1_000`).
+  /// Returns `None` if the token is not an integer literal or does not fit in a `u64`.
+  pub fn numeric_value(&self) -> Option<u64> {
+    let (digits, radix) = self.integer_digits()?;
+    return u64::from_str_radix(&digits, radix).ok();
+  }
+
+  /// Whether the token is written like an integer literal (see `numeric_value`),
+  /// no matter how big it is.
+  pub fn is_integer_literal(&self) -> bool {
+    return self.integer_digits().is_some();
+  }
+
+  /// Splits an integer literal into its radix and its digits, without the prefix
+  /// or the `_` separators. `None` if the token is not an integer literal.
+  fn integer_digits(&self) -> Option<(String, u32)> {
+    // A number always starts with a digit, otherwise `_1` would be a number too.
+    if !self.token.starts_with(|ch: char| ch.is_ascii_digit()) {
+      return None;
+    }
+
+    // The prefix is checked before removing the `_`s, they only separate digits,
+    // so `0_xFF` is not a number.
+    let (digits, radix): (&str, u32) = match self.token.get(0..2) {
+      Some("0x") | Some("0X") => (&self.token[2..], 16),
+      Some("0o") | Some("0O") => (&self.token[2..], 8),
+      Some("0b") | Some("0B") => (&self.token[2..], 2),
+      _ => (self.token.as_str(), 10),
+    };
+    let digits: String = digits.replace('_', "");
+
+    if digits.is_empty() || !digits.chars().all(|ch| ch.is_digit(radix)) {
+      return None;
+    }
+    return Some((digits, radix));
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// Turns the lines of some code into what `tokenize` takes.
+  fn code(lines: &[&str]) -> Vec<String> {
+    lines.iter().map(|line| line.to_string()).collect()
+  }
+
+  /// The text of every token.
+  fn texts(tokens: &[Token]) -> Vec<&str> {
+    tokens.iter().map(|token| token.text()).collect()
+  }
+
+  #[test]
+  fn if_else_without_flag() {
+    let lines = code(&["#if LOUD", "const a str = \"HI\";", "#else", "const a str = \"hi\";", "#endif", "println(a);"]);
+    let tokens = tokenize_with_defines(lines, &HashSet::new());
+
+    assert_eq!(texts(&tokens), ["const", "a", "str", "=", "hi", ";", "println", "(", "a", ")", ";"]);
+    // The lines keep their line numbers.
+    assert_eq!(tokens[0].line, 3);
+    assert_eq!(tokens[6].line, 5);
+  }
+
+  #[test]
+  fn if_else_with_flag() {
+    let lines = code(&["#if LOUD", "const a str = \"HI\";", "#else", "const a str = \"hi\";", "#endif", "println(a);"]);
+    let tokens = tokenize_with_defines(lines, &HashSet::from(["LOUD".to_string()]));
+
+    assert_eq!(texts(&tokens), ["const", "a", "str", "=", "HI", ";", "println", "(", "a", ")", ";"]);
+    assert_eq!(tokens[0].line, 1);
+    assert_eq!(tokens[6].line, 5);
+  }
+
+  #[test]
+  fn merge_two_operators() {
+    let plus = Token::new("+").line(2).token_number(3);
+    let equals = Token::new("=").line(2).token_number(4);
+    let merged = plus.merge(&equals);
+    assert_eq!(merged.text(), "+=");
+    assert_eq!((merged.line, merged.token_number), (2, 3));
+  }
+
+  #[test]
+  fn same_content_ignores_position() {
+    let first = Token::new("let").line(0).token_number(0);
+    let second = Token::new("let").line(4).token_number(2);
+    assert!(first.same_content(&second));
+    assert!(!first.same_content(&Token::new("const")));
+  }
+
+  #[test]
+  fn comment_after_string_with_slashes() {
+    let tokens = tokenize(code(&["let u = \"http://example.com\" // real comment"]));
+    assert_eq!(texts(&tokens), ["let", "u", "=", "http://example.com"]);
+  }
+
+  #[test]
+  fn string_with_spaces_is_one_token() {
+    assert_eq!(texts(&tokenize(code(&["\"a b c\""]))), ["a b c"]);
+    assert_eq!(texts(&tokenize(code(&["println(\"hello  there \");"]))), ["println", "(", "hello  there ", ")", ";"]);
+  }
+
+  #[test]
+  fn adjacent_strings_are_joined() {
+    assert_eq!(texts(&tokenize(code(&["\"foo\" \"bar\""]))), ["foobar"]);
+    // Only when nothing but whitespace is between them.
+    assert_eq!(texts(&tokenize(code(&["\"a\" b \"c\""]))), ["a", "b", "c"]);
+  }
+
+  #[test]
+  fn no_break_space_between_tokens() {
+    let error_count_before: usize = error::error_count();
+    let tokens = tokenize(code(&["inc\u{00A0}var;"]));
+    assert_eq!(texts(&tokens), ["inc", "var", ";"]);
+    assert!(error::error_count() > error_count_before);
+  }
+
+  #[test]
+  fn no_break_space_inside_string() {
+    let tokens = tokenize(code(&["\"a\u{00A0}b\""]));
+    assert_eq!(texts(&tokens), ["a\u{00A0}b"]);
+  }
+
+  #[test]
+  fn shebang_on_first_line_is_skipped() {
+    let tokens = tokenize(code(&["#!/usr/bin/env kathleen", "let x int = 1;"]));
+    assert_eq!(tokens[0].text(), "let");
+    assert_eq!(tokens[0].line, 1);
+  }
+
+  #[test]
+  fn shebang_on_later_line_is_not_skipped() {
+    let tokens = tokenize(code(&["let x int = 1;", "#!/usr/bin/env kathleen"]));
+    assert_eq!(tokens[6].text(), "#");
+    assert_eq!(tokens[6].line, 1);
+  }
+
+  #[test]
+  fn directive_inside_continued_string() {
+    let lines = code(&["const a str = \"abc \\", "#if X \\", "rest\";"]);
+    let tokens = tokenize_with_defines(lines, &HashSet::new());
+    assert_eq!(texts(&tokens), ["const", "a", "str", "=", "abc #if X rest", ";"]);
+    assert_eq!(tokens[4].line, 2);
+  }
+
+  #[test]
+  fn string_continued_on_next_line() {
+    let tokens = tokenize(code(&["const a str = \"abc\\", "  def\";"]));
+    assert_eq!(texts(&tokens), ["const", "a", "str", "=", "abcdef", ";"]);
+  }
+
+  #[test]
+  fn file_ends_in_continued_string() {
+    // Other tests may report errors at the same time, so only check that it went up.
+    let error_count_before: usize = error::error_count();
+    let tokens = tokenize(code(&["const a str = \"abc\\", "  def\\"]));
+    assert!(error::error_count() > error_count_before);
+    // What there is of the string is kept.
+    assert_eq!(tokens.last().unwrap().text(), "abcdef");
+  }
+
+  #[test]
+  fn keywords() {
+    assert!(is_keyword("loop"));
+    assert!(Token::new("let").is_keyword());
+    assert!(!is_keyword("banana"));
+  }
+
+  #[test]
+  fn runs_of_special_characters() {
+    assert_eq!(texts(&tokenize(code(&["((("]))), ["(", "(", "("]);
+    assert_eq!(texts(&tokenize(code(&["+-*/"]))), ["+", "-", "*", "/"]);
+    assert_eq!(texts(&tokenize(code(&["){"]))), [")", "{"]);
+
+    let tokens = tokenize(code(&["loop a{(x)};; [ ]<>!|&,.:^"]));
+    assert_eq!(tokens.len(), 20);
+    assert!(tokens.iter().all(|token| !token.text().is_empty()));
+    // No character is lost either.
+    assert_eq!(texts(&tokens).concat(), "loopa{(x)};;[]<>!|&,.:^");
+  }
+
+  #[test]
+  fn classify_quote_and_whitespace() {
+    assert_eq!(classify_char('"'), CharClass::Quote);
+    assert_eq!(classify_char(' '), CharClass::Whitespace);
+    assert_eq!(classify_char('\t'), CharClass::Whitespace);
+  }
+
+  #[test]
+  fn classify_invisible_characters() {
+    // The no-break space is whitespace for Rust, but must not be treated as such.
+    assert_eq!(classify_char('\u{00A0}'), CharClass::Invisible);
+    assert_eq!(classify_char('\u{200B}'), CharClass::Invisible);
+    for ch in INVISIBLE_CHARS {
+      assert_eq!(classify_char(ch), CharClass::Invisible);
+    }
+  }
+
+  #[test]
+  fn classify_special_characters() {
+    for ch in ['(', ')', '{', '}', '[', ']', '<', '>', '!', '|', '&', ',', '.', ':', ';',
+               '+', '*', '/', '-', '=', '^'] {
+      assert_eq!(classify_char(ch), CharClass::Special);
+    }
+  }
+
+  #[test]
+  fn classify_normal_characters() {
+    assert_eq!(classify_char('a'), CharClass::Normal);
+    assert_eq!(classify_char('Z'), CharClass::Normal);
+    assert_eq!(classify_char('7'), CharClass::Normal);
+    assert_eq!(classify_char('_'), CharClass::Normal);
+  }
+
+  #[test]
+  fn expand_macros_skips_strings_and_comments() {
+    let macros: HashMap<String, String> = HashMap::from([("NAME".to_string(), "greeting".to_string())]);
+
+    let (line, continues_string) = expand_macros("NAME \"NAME //\" NAME", &macros, false);
+    assert_eq!(line, "greeting \"NAME //\" greeting");
+    assert!(!continues_string);
+
+    // A quote in a comment does not start a string.
+    let (line, continues_string) = expand_macros("NAME; / / see \"C:\\", &macros, false);
+    assert_eq!(line, "greeting; / / see \"C:\\");
+    assert!(!continues_string);
+
+    let (_, continues_string) = expand_macros("NAME = \"C:\\", &macros, false);
+    assert!(continues_string);
+  }
+
+  #[test]
+  fn numeric_value_of_integer_literals() {
+    assert_eq!(Token::new("0xFF").numeric_value(), Some(255));
+    assert_eq!(Token::new("0b1010").numeric_value(), Some(10));
+    assert_eq!(Token::new("0o17").numeric_value(), Some(15));
+    assert_eq!(Token::new("1_000").numeric_value(), Some(1000));
+    assert_eq!(Token::new("0xFF_FF").numeric_value(), Some(65535));
+  }
+
+  #[test]
+  fn numeric_value_of_non_integer_literals() {
+    assert_eq!(Token::new("0_xFF").numeric_value(), None);
+    assert_eq!(Token::new("_1").numeric_value(), None);
+    assert_eq!(Token::new("0x").numeric_value(), None);
+    assert_eq!(Token::new("0b102").numeric_value(), None);
+    assert_eq!(Token::new("banana").numeric_value(), None);
+  }
+
+  #[test]
+  fn too_big_integer_literal_is_still_a_literal() {
+    let token = Token::new("18446744073709551616");
+    assert_eq!(token.numeric_value(), None);
+    assert!(token.is_integer_literal());
+  }
+}